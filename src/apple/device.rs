@@ -0,0 +1,176 @@
+use super::target::Target;
+use std::collections::BTreeSet;
+
+/// Where a [`Device`] was discovered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeviceKind {
+    /// Detected via `ios-deploy`, i.e. physically plugged in (or paired over
+    /// Wi-Fi).
+    Physical,
+    /// Detected via `xcrun simctl`, i.e. a simulator known to this Mac.
+    Simulator,
+}
+
+/// Whether a [`Device`] is reachable right now, or merely known from a
+/// previous detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConnectionState {
+    /// A physical device `ios-deploy` currently sees plugged in (or paired).
+    Connected,
+    /// A simulator that's currently running.
+    Booted,
+    /// A simulator that exists but isn't running.
+    Shutdown,
+    /// Known (e.g. from a cache) but not currently reachable at all.
+    Unavailable,
+}
+
+impl ConnectionState {
+    pub fn is_active(self) -> bool {
+        matches!(self, Self::Connected | Self::Booted)
+    }
+}
+
+// Identity (`Eq`) and ordering (`Ord`) agree by deriving both over the same
+// fields in the same order; `BTreeSet` relies on that to treat two `Device`s
+// as "the same" only when every field matches. Presentation order (active
+// devices first) is a separate concern handled by `sorted_for_display`
+// below, not by this type's natural ordering.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Device<'a> {
+    id: String,
+    name: String,
+    model: String,
+    target: Target<'a>,
+    kind: DeviceKind,
+    connection_state: ConnectionState,
+}
+
+impl<'a> Device<'a> {
+    pub fn new(id: String, name: String, model: String, target: Target<'a>) -> Self {
+        Self::with_state(id, name, model, target, DeviceKind::Physical, ConnectionState::Connected)
+    }
+
+    pub fn new_simulator(
+        id: String,
+        name: String,
+        model: String,
+        target: Target<'a>,
+        connection_state: ConnectionState,
+    ) -> Self {
+        Self::with_state(id, name, model, target, DeviceKind::Simulator, connection_state)
+    }
+
+    /// Reconstructs a device previously seen but not currently detected,
+    /// e.g. one loaded from the on-disk cache.
+    pub fn from_cache(id: String, name: String, model: String, target: Target<'a>) -> Self {
+        Self::with_state(id, name, model, target, DeviceKind::Physical, ConnectionState::Unavailable)
+    }
+
+    fn with_state(
+        id: String,
+        name: String,
+        model: String,
+        target: Target<'a>,
+        kind: DeviceKind,
+        connection_state: ConnectionState,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            model,
+            target,
+            kind,
+            connection_state,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn target(&self) -> &Target<'a> {
+        &self.target
+    }
+
+    pub fn kind(&self) -> DeviceKind {
+        self.kind
+    }
+
+    pub fn is_simulator(&self) -> bool {
+        self.kind == DeviceKind::Simulator
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection_state
+    }
+}
+
+/// Returns `devices` as a `Vec` ordered for display: devices that are
+/// reachable right now come first, then ties are broken by name and id for
+/// a stable, human-friendly order within each state.
+///
+/// `device_list`/`watch_devices` hand back the raw `BTreeSet` (ordered by
+/// id, per `Device`'s natural `Ord`) rather than calling this themselves;
+/// presentation order is a decision for whatever's printing the list, not
+/// for detection. Callers that render devices to a user should run the set
+/// through this first.
+pub fn sorted_for_display<'a>(devices: &BTreeSet<Device<'a>>) -> Vec<Device<'a>> {
+    let mut devices: Vec<_> = devices.iter().cloned().collect();
+    devices.sort_by(|a, b| {
+        b.connection_state
+            .is_active()
+            .cmp(&a.connection_state.is_active())
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    devices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apple::target::Target;
+
+    fn device(id: &str, name: &str, state: ConnectionState) -> Device<'static> {
+        Device::new_simulator(
+            id.to_owned(),
+            name.to_owned(),
+            "Simulator".to_owned(),
+            Target::for_arch("arm64").unwrap(),
+            state,
+        )
+    }
+
+    #[test]
+    fn active_devices_sort_first_for_display() {
+        let mut devices = BTreeSet::new();
+        devices.insert(device("b", "Shutdown Sim", ConnectionState::Shutdown));
+        devices.insert(device("a", "Booted Sim", ConnectionState::Booted));
+        devices.insert(device("c", "Unavailable Sim", ConnectionState::Unavailable));
+
+        let sorted = sorted_for_display(&devices);
+        let ids: Vec<_> = sorted.iter().map(Device::id).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn ord_agrees_with_eq() {
+        let connected = device("same-id", "Name", ConnectionState::Booted);
+        let mut shutdown = connected.clone();
+        shutdown.connection_state = ConnectionState::Shutdown;
+
+        // Differing only in `connection_state`, these must NOT compare
+        // equal under `Ord`/`Eq` agreement rules used by `BTreeSet`.
+        assert_ne!(connected, shutdown);
+        assert_ne!(connected.cmp(&shutdown), std::cmp::Ordering::Equal);
+    }
+}
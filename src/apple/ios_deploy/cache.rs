@@ -0,0 +1,128 @@
+use crate::{
+    apple::{device::Device, target::Target},
+    util::cli::{Report, Reportable},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, path::PathBuf};
+
+#[derive(Debug)]
+pub enum CacheError {
+    OpenFailed(sled::Error),
+    IoFailed(sled::Error),
+    NoCacheDir,
+}
+
+impl Reportable for CacheError {
+    fn report(&self) -> Report {
+        let msg = "Failed to access the iOS device cache";
+        match self {
+            Self::OpenFailed(err) => Report::error(msg, format!("Failed to open cache database: {}", err)),
+            Self::IoFailed(err) => Report::error(msg, format!("Cache read/write failed: {}", err)),
+            Self::NoCacheDir => Report::error(msg, "Couldn't determine a cache directory for this user"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedDevice {
+    device_name: String,
+    model_name: String,
+    model_arch: String,
+}
+
+/// A small on-disk, write-through cache of device metadata, keyed by UDID,
+/// so `cargo-mobile` can show friendly names without re-querying
+/// `ios-deploy` every time.
+pub struct DeviceCache {
+    db: sled::Db,
+}
+
+impl DeviceCache {
+    fn cache_path() -> Result<PathBuf, CacheError> {
+        directories::ProjectDirs::from("", "", "cargo-mobile")
+            .map(|dirs| dirs.cache_dir().join("ios-devices"))
+            .ok_or(CacheError::NoCacheDir)
+    }
+
+    pub fn open() -> Result<Self, CacheError> {
+        let db = sled::open(Self::cache_path()?).map_err(CacheError::OpenFailed)?;
+        Ok(Self { db })
+    }
+
+    /// Write-through: called after every successful detection so the cache
+    /// stays current.
+    pub fn record(&self, id: &str, device_name: &str, model_name: &str, model_arch: &str) -> Result<(), CacheError> {
+        let entry = CachedDevice {
+            device_name: device_name.to_owned(),
+            model_name: model_name.to_owned(),
+            model_arch: model_arch.to_owned(),
+        };
+        let bytes = serde_json::to_vec(&entry).expect("developer error: `CachedDevice` isn't serializable");
+        self.db.insert(id, bytes).map_err(CacheError::IoFailed)?;
+        self.db.flush().map_err(CacheError::IoFailed)?;
+        Ok(())
+    }
+
+    /// Returns every cached device, reconstructed as `Device`s flagged
+    /// `ConnectionState::Unavailable`, for use when a live detection comes
+    /// back empty.
+    pub fn all<'a>(&self) -> Result<BTreeSet<Device<'a>>, CacheError> {
+        let mut devices = BTreeSet::new();
+        for entry in self.db.iter() {
+            let (id, bytes) = entry.map_err(CacheError::IoFailed)?;
+            let id = String::from_utf8_lossy(&id).into_owned();
+            // A corrupt or unrecognized-arch entry shouldn't nuke the whole
+            // fallback list; just drop that one stale entry and keep going.
+            let cached: CachedDevice = match serde_json::from_slice(&bytes) {
+                Ok(cached) => cached,
+                Err(err) => {
+                    log::warn!("skipping corrupt cache entry {:?}: {}", id, err);
+                    continue;
+                }
+            };
+            if let Some(target) = Target::for_arch(&cached.model_arch) {
+                devices.insert(Device::from_cache(id, cached.device_name, cached.model_name, target));
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Removes cached entries whose UDID isn't in `keep`, e.g. after a
+    /// successful detection that should supersede older entries.
+    pub fn clear_stale(&self, keep: &BTreeSet<String>) -> Result<(), CacheError> {
+        let stale: Vec<_> = self
+            .db
+            .iter()
+            .keys()
+            .filter_map(|key| key.ok())
+            .map(|key| String::from_utf8_lossy(&key).into_owned())
+            .filter(|id| !keep.contains(id))
+            .collect();
+        for id in stale {
+            self.db.remove(id).map_err(CacheError::IoFailed)?;
+        }
+        self.db.flush().map_err(CacheError::IoFailed)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache() -> DeviceCache {
+        let db = sled::Config::new().temporary(true).open().expect("failed to open temp sled db");
+        DeviceCache { db }
+    }
+
+    #[test]
+    fn all_skips_corrupt_entries_but_keeps_the_rest() {
+        let cache = temp_cache();
+        cache.db.insert("bad", b"not json".as_slice()).unwrap();
+        cache.record("good", "iPhone", "iPhone15,2", "arm64").unwrap();
+
+        let devices: Vec<_> = cache.all().unwrap().into_iter().collect();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].id(), "good");
+    }
+}
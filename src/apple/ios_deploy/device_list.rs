@@ -1,16 +1,38 @@
-use super::{DeviceInfo, Event};
+use super::{cache::DeviceCache, simctl, DeviceInfo, Event, EventError};
 use crate::{
     apple::{device::Device, target::Target},
     env::{Env, ExplicitEnv as _},
     util::cli::{Report, Reportable},
 };
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, time::Duration};
+
+/// Options controlling how `ios-deploy --detect` is invoked.
+///
+/// The default matches today's hardcoded behavior: no Wi-Fi-paired devices,
+/// 1 second timeout. Networked devices take longer to show up, so callers
+/// that want to see them should pass a longer timeout too.
+#[derive(Debug, Clone, Copy)]
+pub struct DeviceListOpts {
+    pub timeout: Duration,
+    pub include_wifi: bool,
+}
+
+impl Default for DeviceListOpts {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            include_wifi: false,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum DeviceListError {
     DetectionFailed(bossy::Error),
     InvalidUtf8(std::str::Utf8Error),
-    ArchInvalid(String),
+    /// `ios-deploy` reported one or more errors (e.g. "device is locked",
+    /// "trust this computer") and no devices were successfully detected.
+    Remote(Vec<EventError>),
 }
 
 impl Reportable for DeviceListError {
@@ -24,48 +46,202 @@ impl Reportable for DeviceListError {
             Self::InvalidUtf8(err) => {
                 Report::error(msg, format!("Device info contained invalid UTF-8: {}", err))
             }
-            Self::ArchInvalid(arch) => {
-                Report::error(msg, format!("{:?} isn't a valid target arch.", arch))
+            Self::Remote(errors) => {
+                let details = errors
+                    .iter()
+                    .map(|err| format!("[{}] {}", err.code, err.status))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                Report::error(msg, details)
             }
         }
     }
 }
 
-fn parse_device_list<'a>(output: &bossy::Output) -> Result<BTreeSet<Device<'a>>, DeviceListError> {
-    Event::parse_list(output.stdout_str().map_err(DeviceListError::InvalidUtf8)?)
-        .into_iter()
-        .flat_map(|event| event.device_info().cloned())
-        .map(
-            |DeviceInfo {
-                 device_identifier,
-                 device_name,
-                 model_arch,
-                 model_name,
-             }| {
-                Target::for_arch(&model_arch)
-                    .map(|target| Device::new(device_identifier, device_name, model_name, target))
-                    .ok_or_else(|| DeviceListError::ArchInvalid(model_arch))
-            },
-        )
-        .collect::<Result<_, _>>()
+// Splits `ios-deploy --json` output into the `DeviceInfo`s and `EventError`s
+// it reported. Each group keeps its input order; how the two were interleaved
+// is lost (callers only care about each group).
+fn split_events(raw: &str) -> (Vec<DeviceInfo>, Vec<EventError>) {
+    let events = Event::parse_list(raw);
+    let errors = events.iter().filter_map(Event::error).cloned().collect();
+    let infos = events.into_iter().filter_map(|event| event.device_info().cloned()).collect();
+    (infos, errors)
 }
 
-pub fn device_list<'a>(env: &Env) -> Result<BTreeSet<Device<'a>>, DeviceListError> {
-    let result = bossy::Command::pure_parse("ios-deploy --detect --timeout 1 --json --no-wifi")
+// `ios-deploy --detect` only ever reports hardware it can currently see, so
+// every `Device` it produces is `ConnectionState::Connected` (the default
+// applied by `Device::new`); there's no "known but unplugged" case here yet.
+//
+// `cache`, when given, is written through with every device's metadata so a
+// later, empty detection can still show friendly names while it's offline.
+//
+// Error-class events are returned alongside the (possibly empty) device set
+// rather than as an `Err`, so `physical_device_list` gets a chance to try
+// the cache fallback first; it's the one that decides whether the errors
+// are worth surfacing to the caller.
+fn parse_device_list<'a>(
+    output: &bossy::Output,
+    cache: Option<&DeviceCache>,
+) -> Result<(BTreeSet<Device<'a>>, Vec<EventError>), DeviceListError> {
+    let (infos, errors) = split_events(output.stdout_str().map_err(DeviceListError::InvalidUtf8)?);
+
+    // An unrecognized arch on one device (e.g. a brand-new chip `Target`
+    // doesn't know about yet) shouldn't take out every other device in the
+    // list; just drop that one entry and keep going, same as
+    // `DeviceCache::all` does for a stale cached arch.
+    let mut devices = BTreeSet::new();
+    for DeviceInfo {
+        device_identifier,
+        device_name,
+        model_arch,
+        model_name,
+    } in infos
+    {
+        if let Some(cache) = cache {
+            if let Err(err) = cache.record(&device_identifier, &device_name, &model_name, &model_arch) {
+                log::warn!("failed to update device cache: {:?}", err.report());
+            }
+        }
+        match Target::for_arch(&model_arch) {
+            Some(target) => {
+                devices.insert(Device::new(device_identifier, device_name, model_name, target));
+            }
+            None => log::warn!(
+                "skipping device {:?} ({:?}): {:?} isn't a valid target arch",
+                device_name,
+                device_identifier,
+                model_arch
+            ),
+        }
+    }
+
+    Ok((devices, errors))
+}
+
+// `ios-deploy --timeout` only takes whole seconds; round up rather than
+// truncating so a sub-second `Duration` (e.g. 500ms) doesn't silently
+// become a 0-second timeout.
+fn timeout_secs(timeout: Duration) -> u64 {
+    timeout.as_secs_f64().ceil().max(1.0) as u64
+}
+
+fn physical_device_list<'a>(env: &Env, opts: &DeviceListOpts) -> Result<BTreeSet<Device<'a>>, DeviceListError> {
+    let cache = match DeviceCache::open() {
+        Ok(cache) => Some(cache),
+        Err(err) => {
+            log::warn!("failed to open device cache: {:?}", err.report());
+            None
+        }
+    };
+
+    let wifi_flag = if opts.include_wifi { "" } else { " --no-wifi" };
+    let command = format!(
+        "ios-deploy --detect --timeout {} --json{}",
+        timeout_secs(opts.timeout),
+        wifi_flag,
+    );
+    let result = bossy::Command::pure_parse(&command)
         .with_env_vars(env.explicit_env())
         .run_and_wait_for_output();
-    match result {
-        Ok(output) => parse_device_list(&output),
+    let (devices, errors) = match result {
+        Ok(output) => parse_device_list(&output, cache.as_ref())?,
         Err(err) => {
             let output = err
                 .output()
                 .expect("developer error: `ios-deploy --detect` output wasn't collected");
             if output.stdout().is_empty() && output.stderr().is_empty() {
                 log::info!("device detection returned a non-zero exit code, but stdout and stderr are both empty; interpreting as a successful run with no devices connected");
-                Ok(Default::default())
+                (Default::default(), Vec::new())
             } else {
-                Err(DeviceListError::DetectionFailed(err))
+                return Err(DeviceListError::DetectionFailed(err));
             }
         }
+    };
+
+    if devices.is_empty() {
+        if let Some(cache) = &cache {
+            match cache.all() {
+                Ok(cached) if !cached.is_empty() => {
+                    log::info!(
+                        "no devices detected; showing {} last-known device(s) as offline",
+                        cached.len()
+                    );
+                    return Ok(cached);
+                }
+                Ok(_) => {}
+                Err(err) => log::warn!("failed to read device cache: {:?}", err.report()),
+            }
+        }
+
+        // The cache didn't have anything useful either; if `ios-deploy`
+        // reported errors, that's a more actionable failure than the
+        // generic "no devices connected" case.
+        if !errors.is_empty() {
+            return Err(DeviceListError::Remote(errors));
+        }
+    } else {
+        if let Some(cache) = &cache {
+            let ids = devices.iter().map(|device| device.id().to_owned()).collect();
+            if let Err(err) = cache.clear_stale(&ids) {
+                log::warn!("failed to prune stale device cache entries: {:?}", err.report());
+            }
+        }
+        for err in &errors {
+            log::warn!("ios-deploy: [{}] {}", err.code, err.status);
+        }
+    }
+
+    Ok(devices)
+}
+
+/// Detects both physically-connected devices (via `ios-deploy`) and
+/// simulators known to this Mac (via `xcrun simctl`), merging them into a
+/// single list. A device's [`DeviceKind`](crate::apple::device::DeviceKind)
+/// tells the caller which of the two it came from.
+///
+/// Simulator detection failing (e.g. `simctl` missing) isn't fatal; we fall
+/// back to physical devices only, since that's strictly more useful than
+/// failing outright.
+pub fn device_list<'a>(env: &Env, opts: &DeviceListOpts) -> Result<BTreeSet<Device<'a>>, DeviceListError> {
+    let mut devices = physical_device_list(env, opts)?;
+    match simctl::simulator_list(env) {
+        Ok(simulators) => devices.extend(simulators),
+        Err(err) => log::warn!("failed to enumerate iOS simulators: {:?}", err.report()),
+    }
+    Ok(devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_secs_rounds_up_sub_second_durations() {
+        assert_eq!(timeout_secs(Duration::from_millis(500)), 1);
+        assert_eq!(timeout_secs(Duration::from_secs(1)), 1);
+        assert_eq!(timeout_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(timeout_secs(Duration::from_secs(5)), 5);
+    }
+
+    #[test]
+    fn split_events_separates_devices_from_errors() {
+        let raw = concat!(
+            r#"{"Event":"DeviceDetected","DeviceIdentifier":"abc","DeviceName":"iPhone","modelArch":"arm64","ModelName":"iPhone15,2"}"#,
+            "\n",
+            r#"{"Event":"Error","Code":-13,"Status":"device is locked"}"#,
+        );
+
+        let (infos, errors) = split_events(raw);
+        assert_eq!(infos.len(), 1);
+        assert_eq!(infos[0].device_identifier, "abc");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].status, "device is locked");
+    }
+
+    #[test]
+    fn split_events_ignores_unrecognized_lines() {
+        let (infos, errors) = split_events("not json\n{\"Event\":\"SomethingElse\"}");
+        assert!(infos.is_empty());
+        assert!(errors.is_empty());
     }
 }
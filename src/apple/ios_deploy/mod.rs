@@ -0,0 +1,63 @@
+pub mod cache;
+pub mod device_list;
+pub mod simctl;
+pub mod watch;
+
+use serde::Deserialize;
+
+/// Metadata `ios-deploy` reports about a connected device.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceInfo {
+    #[serde(rename = "DeviceIdentifier")]
+    pub device_identifier: String,
+    #[serde(rename = "DeviceName")]
+    pub device_name: String,
+    #[serde(rename = "modelArch")]
+    pub model_arch: String,
+    #[serde(rename = "ModelName")]
+    pub model_name: String,
+}
+
+/// An error or warning `ios-deploy` reported about a device (e.g. "device is
+/// locked", "trust this computer").
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventError {
+    #[serde(rename = "Code")]
+    pub code: i64,
+    #[serde(rename = "Status")]
+    pub status: String,
+}
+
+/// A single line of `ios-deploy --json` output.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "Event")]
+pub enum Event {
+    #[serde(rename = "DeviceDetected")]
+    Device(DeviceInfo),
+    #[serde(rename = "Error")]
+    Error(EventError),
+}
+
+impl Event {
+    pub fn device_info(&self) -> Option<&DeviceInfo> {
+        match self {
+            Self::Device(info) => Some(info),
+            Self::Error(_) => None,
+        }
+    }
+
+    pub fn error(&self) -> Option<&EventError> {
+        match self {
+            Self::Error(err) => Some(err),
+            Self::Device(_) => None,
+        }
+    }
+
+    /// `ios-deploy --json` emits one JSON object per line; lines we don't
+    /// recognize yet are skipped rather than failing the whole parse.
+    pub fn parse_list(raw: &str) -> Vec<Self> {
+        raw.lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+}
@@ -0,0 +1,236 @@
+use super::super::{
+    device::{ConnectionState, Device},
+    target::Target,
+};
+use crate::{
+    env::{Env, ExplicitEnv as _},
+    util::cli::{Report, Reportable},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Apple Silicon Macs run `arm64` simulators; Intel Macs run `x86_64` ones.
+#[cfg(target_arch = "aarch64")]
+const SIMULATOR_ARCH: &str = "arm64";
+#[cfg(not(target_arch = "aarch64"))]
+const SIMULATOR_ARCH: &str = "x86_64";
+
+#[derive(Debug)]
+pub enum SimctlError {
+    DetectionFailed(bossy::Error),
+    InvalidUtf8(std::str::Utf8Error),
+    InvalidJson(serde_json::Error),
+    ArchInvalid(String),
+}
+
+impl Reportable for SimctlError {
+    fn report(&self) -> Report {
+        let msg = "Failed to detect iOS simulators";
+        match self {
+            Self::DetectionFailed(err) => Report::error(
+                msg,
+                format!("Failed to request device list from `xcrun simctl`: {}", err),
+            ),
+            Self::InvalidUtf8(err) => {
+                Report::error(msg, format!("Simulator info contained invalid UTF-8: {}", err))
+            }
+            Self::InvalidJson(err) => {
+                Report::error(msg, format!("Failed to parse `simctl` output: {}", err))
+            }
+            Self::ArchInvalid(arch) => {
+                Report::error(msg, format!("{:?} isn't a valid target arch.", arch))
+            }
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SimctlDevice {
+    udid: String,
+    name: String,
+    state: String,
+    #[serde(rename = "isAvailable", default)]
+    is_available: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SimctlDeviceList {
+    devices: BTreeMap<String, Vec<SimctlDevice>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SimctlRuntime {
+    identifier: String,
+    name: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SimctlRuntimeList {
+    runtimes: Vec<SimctlRuntime>,
+}
+
+fn connection_state(raw_state: &str) -> ConnectionState {
+    match raw_state {
+        "Booted" => ConnectionState::Booted,
+        _ => ConnectionState::Shutdown,
+    }
+}
+
+// The runtimes list isn't always available (older Xcode, flaky `simctl`),
+// so fall back to deriving a label straight from the runtime identifier,
+// e.g. "com.apple.CoreSimulator.SimRuntime.iOS-17-0" -> "iOS 17.0".
+fn runtime_label_from_identifier(identifier: &str) -> String {
+    let suffix = identifier.rsplit('.').next().unwrap_or(identifier);
+    match suffix.split_once('-') {
+        Some((os, version)) => format!("{} {}", os, version.replace('-', ".")),
+        None => suffix.to_owned(),
+    }
+}
+
+fn run_simctl(env: &Env, args: &str) -> Result<bossy::Output, SimctlError> {
+    bossy::Command::pure_parse(&format!("xcrun simctl list --json {}", args))
+        .with_env_vars(env.explicit_env())
+        .run_and_wait_for_output()
+        .map_err(SimctlError::DetectionFailed)
+}
+
+/// Maps each runtime identifier (e.g.
+/// `"com.apple.CoreSimulator.SimRuntime.iOS-17-0"`) to its human-readable
+/// name (e.g. `"iOS 17.0"`), via `xcrun simctl list --json runtimes`.
+///
+/// Failing to fetch this isn't fatal to simulator detection as a whole;
+/// callers fall back to deriving a label from the identifier itself.
+fn runtime_names(env: &Env) -> BTreeMap<String, String> {
+    let output = match run_simctl(env, "runtimes") {
+        Ok(output) => output,
+        Err(err) => {
+            log::warn!("failed to list simulator runtimes: {:?}", err.report());
+            return BTreeMap::new();
+        }
+    };
+    let raw = match output.stdout_str() {
+        Ok(raw) => raw,
+        Err(err) => {
+            log::warn!("`simctl list runtimes` output contained invalid UTF-8: {}", err);
+            return BTreeMap::new();
+        }
+    };
+    match serde_json::from_str::<SimctlRuntimeList>(raw) {
+        Ok(list) => list.runtimes.into_iter().map(|rt| (rt.identifier, rt.name)).collect(),
+        Err(err) => {
+            log::warn!("failed to parse `simctl list runtimes` output: {}", err);
+            BTreeMap::new()
+        }
+    }
+}
+
+fn parse_simctl_devices<'a>(
+    output: &bossy::Output,
+    runtime_names: &BTreeMap<String, String>,
+) -> Result<BTreeSet<Device<'a>>, SimctlError> {
+    let raw = output.stdout_str().map_err(SimctlError::InvalidUtf8)?;
+    devices_from_json(raw, runtime_names)
+}
+
+fn devices_from_json<'a>(
+    raw: &str,
+    runtime_names: &BTreeMap<String, String>,
+) -> Result<BTreeSet<Device<'a>>, SimctlError> {
+    let list: SimctlDeviceList = serde_json::from_str(raw).map_err(SimctlError::InvalidJson)?;
+    let target = Target::for_arch(SIMULATOR_ARCH)
+        .ok_or_else(|| SimctlError::ArchInvalid(SIMULATOR_ARCH.to_owned()))?;
+
+    let mut devices = BTreeSet::new();
+    for (runtime_id, runtime_devices) in list.devices {
+        let runtime_label = runtime_names
+            .get(&runtime_id)
+            .cloned()
+            .unwrap_or_else(|| runtime_label_from_identifier(&runtime_id));
+        for device in runtime_devices {
+            if !device.is_available {
+                continue;
+            }
+            let state = connection_state(&device.state);
+            devices.insert(Device::new_simulator(
+                device.udid,
+                device.name,
+                runtime_label.clone(),
+                target.clone(),
+                state,
+            ));
+        }
+    }
+    Ok(devices)
+}
+
+/// Enumerates available iOS Simulators via `xcrun simctl list --json
+/// devices`, labelling each with its runtime (e.g. `"iOS 17.0"`) so two
+/// simulators that share a device name but run different iOS versions
+/// remain distinguishable.
+pub fn simulator_list<'a>(env: &Env) -> Result<BTreeSet<Device<'a>>, SimctlError> {
+    let runtime_names = runtime_names(env);
+    let output = run_simctl(env, "devices")?;
+    parse_simctl_devices(&output, &runtime_names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_state_maps_booted_and_shutdown() {
+        assert_eq!(connection_state("Booted"), ConnectionState::Booted);
+        assert_eq!(connection_state("Shutdown"), ConnectionState::Shutdown);
+        assert_eq!(connection_state("Creating"), ConnectionState::Shutdown);
+    }
+
+    #[test]
+    fn runtime_label_falls_back_to_parsing_identifier() {
+        assert_eq!(
+            runtime_label_from_identifier("com.apple.CoreSimulator.SimRuntime.iOS-17-0"),
+            "iOS 17.0"
+        );
+        assert_eq!(
+            runtime_label_from_identifier("com.apple.CoreSimulator.SimRuntime.watchOS-10-0"),
+            "watchOS 10.0"
+        );
+    }
+
+    #[test]
+    fn devices_from_json_filters_unavailable_and_labels_by_runtime() {
+        let raw = r#"{
+            "devices": {
+                "com.apple.CoreSimulator.SimRuntime.iOS-17-0": [
+                    {"udid": "aaa", "name": "iPhone 15", "state": "Booted", "isAvailable": true},
+                    {"udid": "bbb", "name": "Old iPhone", "state": "Shutdown", "isAvailable": false}
+                ]
+            }
+        }"#;
+        let runtime_names = BTreeMap::new();
+        let devices = devices_from_json(raw, &runtime_names).unwrap();
+
+        assert_eq!(devices.len(), 1);
+        let device = devices.iter().next().unwrap();
+        assert_eq!(device.id(), "aaa");
+        assert_eq!(device.model(), "iOS 17.0");
+        assert_eq!(device.connection_state(), ConnectionState::Booted);
+    }
+
+    #[test]
+    fn devices_from_json_prefers_runtime_list_name() {
+        let raw = r#"{
+            "devices": {
+                "com.apple.CoreSimulator.SimRuntime.iOS-17-0": [
+                    {"udid": "aaa", "name": "iPhone 15", "state": "Booted", "isAvailable": true}
+                ]
+            }
+        }"#;
+        let mut runtime_names = BTreeMap::new();
+        runtime_names.insert(
+            "com.apple.CoreSimulator.SimRuntime.iOS-17-0".to_owned(),
+            "iOS 17.0 Beta".to_owned(),
+        );
+        let devices = devices_from_json(raw, &runtime_names).unwrap();
+
+        assert_eq!(devices.iter().next().unwrap().model(), "iOS 17.0 Beta");
+    }
+}
@@ -0,0 +1,105 @@
+use super::device_list::{self, DeviceListOpts};
+use crate::{
+    apple::device::{ConnectionState, Device},
+    env::Env,
+    util::cli::Reportable,
+};
+use std::{
+    collections::BTreeMap,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+/// A change observed between two device-list snapshots.
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+    DeviceConnected(Device<'static>),
+    DeviceDisconnected(String),
+}
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn by_id<'a>(devices: impl IntoIterator<Item = Device<'a>>) -> BTreeMap<String, Device<'a>> {
+    devices.into_iter().map(|device| (device.id().to_owned(), device)).collect()
+}
+
+/// Spawns a background thread that polls [`device_list::device_list`] every
+/// [`POLL_INTERVAL`], diffing successive snapshots and emitting
+/// [`DeviceEvent`]s over the returned channel as devices connect and
+/// disconnect. The watcher keeps re-polling until a send fails because the
+/// receiver was dropped; while there's nothing to send, it keeps polling.
+///
+/// Devices in `ConnectionState::Unavailable` (last-known devices that
+/// `device_list` falls back to from its cache) are treated as absent, so an
+/// unplugged phone yields a disconnect rather than lingering as offline.
+///
+/// Snapshots are diffed by UDID rather than by full device equality, so a
+/// device that stays present while its `ConnectionState` changes between
+/// polls (e.g. a simulator going `Booted` -> `Shutdown` from Xcode) isn't
+/// mistaken for a disconnect followed by an unrelated connect.
+pub fn watch_devices(env: Env) -> Receiver<DeviceEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let opts = DeviceListOpts::default();
+        let mut known: BTreeMap<String, Device<'static>> = BTreeMap::new();
+        loop {
+            let current = match device_list::device_list(&env, &opts) {
+                Ok(devices) => by_id(
+                    devices
+                        .into_iter()
+                        .filter(|device| device.connection_state() != ConnectionState::Unavailable),
+                ),
+                Err(err) => {
+                    log::warn!("device watch: detection failed: {:?}", err.report());
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+            };
+
+            for (id, device) in &current {
+                if !known.contains_key(id) && tx.send(DeviceEvent::DeviceConnected(device.clone())).is_err() {
+                    return;
+                }
+            }
+            for id in known.keys() {
+                if !current.contains_key(id) && tx.send(DeviceEvent::DeviceDisconnected(id.clone())).is_err() {
+                    return;
+                }
+            }
+
+            known = current;
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apple::{device::ConnectionState, target::Target};
+
+    fn sim(id: &str, state: ConnectionState) -> Device<'static> {
+        Device::new_simulator(
+            id.to_owned(),
+            "Sim".to_owned(),
+            "Simulator".to_owned(),
+            Target::for_arch("arm64").unwrap(),
+            state,
+        )
+    }
+
+    #[test]
+    fn connection_state_change_alone_is_not_a_reconnect() {
+        let before = by_id([sim("udid-1", ConnectionState::Booted)]);
+        let after = by_id([sim("udid-1", ConnectionState::Shutdown)]);
+
+        assert!(before.contains_key("udid-1"));
+        assert!(after.contains_key("udid-1"));
+        // Diffing by id (as `watch_devices` does), the same UDID present in
+        // both snapshots yields no connect/disconnect event, regardless of
+        // its `ConnectionState` changing.
+        assert_eq!(before.keys().collect::<Vec<_>>(), after.keys().collect::<Vec<_>>());
+    }
+}